@@ -0,0 +1,44 @@
+use crate::AppState;
+use crate::order_book::{MarketId, OrderBook};
+use axum::Json;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use serde::Deserialize;
+use std::sync::Arc;
+
+#[derive(Deserialize)]
+pub struct RegisterMarketRequest {
+    pub base: String,
+    pub quote: String,
+}
+
+impl From<RegisterMarketRequest> for MarketId {
+    fn from(request: RegisterMarketRequest) -> Self {
+        MarketId::new(request.base, request.quote)
+    }
+}
+
+/// Registers a new market for its `{ base, quote }` pair, instantiating a
+/// fresh, independently-locked order book for it if one isn't already
+/// registered. Re-registering an existing pair is a no-op.
+pub async fn register_market_handler<O: OrderBook + Default>(
+    State(state): State<AppState<O>>,
+    Json(request): Json<RegisterMarketRequest>,
+) -> impl IntoResponse {
+    let market_id: MarketId = request.into();
+    state
+        .markets
+        .write()
+        .await
+        .entry(market_id.clone())
+        .or_insert_with(|| Arc::new(O::default()));
+    (StatusCode::OK, Json(market_id))
+}
+
+pub async fn list_markets_handler<O: OrderBook>(
+    State(state): State<AppState<O>>,
+) -> impl IntoResponse {
+    let markets: Vec<MarketId> = state.markets.read().await.keys().cloned().collect();
+    (StatusCode::OK, Json(markets))
+}
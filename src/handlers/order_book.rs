@@ -1,43 +1,231 @@
 use crate::AppState;
-use crate::order_book::{Order, OrderBook, OrderSide};
+use crate::order_book::{
+    FeedMessage, Level, MarketId, Order, OrderBook, OrderSide, OrderType, TimeInForce,
+};
 use axum::Json;
-use axum::extract::State;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path, Query, State};
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
+use log::warn;
 use serde::Deserialize;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+/// Looks up the order book registered under `market_id` (a raw
+/// `BASE-QUOTE` path segment). Returns `404 Not Found` for a malformed or
+/// unregistered market id.
+async fn resolve_market<O: OrderBook>(
+    state: &AppState<O>,
+    market_id: &str,
+) -> Result<Arc<O>, StatusCode> {
+    let market_id: MarketId = market_id.parse().map_err(|_| StatusCode::NOT_FOUND)?;
+    state
+        .markets
+        .read()
+        .await
+        .get(&market_id)
+        .cloned()
+        .ok_or(StatusCode::NOT_FOUND)
+}
 
 #[derive(Deserialize)]
 pub struct PlaceOrderRequest {
     pub id: u64,
     pub side: OrderSide,
+    #[serde(default = "default_order_type")]
+    pub order_type: OrderType,
+    #[serde(default = "default_time_in_force")]
+    pub time_in_force: TimeInForce,
+    #[serde(default)]
+    pub valid_to: Option<u128>,
     pub price: u64,
     pub quantity: u64,
 }
 
+fn default_order_type() -> OrderType {
+    OrderType::Limit
+}
+
+fn default_time_in_force() -> TimeInForce {
+    TimeInForce::Gtc
+}
+
 impl From<PlaceOrderRequest> for Order {
     fn from(request: PlaceOrderRequest) -> Self {
-        Order::new(request.id, request.side, request.price, request.quantity)
+        Order::new(
+            request.id,
+            request.side,
+            request.order_type,
+            request.time_in_force,
+            request.valid_to,
+            request.price,
+            request.quantity,
+        )
     }
 }
 
 pub async fn place_order_handler<O: OrderBook>(
-    State(AppState { order_book }): State<AppState<O>>,
+    State(state): State<AppState<O>>,
+    Path(market_id): Path<String>,
     Json(request): Json<PlaceOrderRequest>,
 ) -> impl IntoResponse {
+    let order_book = match resolve_market(&state, &market_id).await {
+        Ok(order_book) => order_book,
+        Err(status) => return status.into_response(),
+    };
     let trades = order_book.place_order(request.into()).await;
-    (StatusCode::OK, Json(trades))
+    (StatusCode::OK, Json(trades)).into_response()
 }
 
 pub async fn best_buy_handler<O: OrderBook>(
-    State(AppState { order_book }): State<AppState<O>>,
+    State(state): State<AppState<O>>,
+    Path(market_id): Path<String>,
 ) -> impl IntoResponse {
+    let order_book = match resolve_market(&state, &market_id).await {
+        Ok(order_book) => order_book,
+        Err(status) => return status.into_response(),
+    };
     let best_order = order_book.best_buy().await;
-    (StatusCode::OK, Json(best_order))
+    (StatusCode::OK, Json(best_order)).into_response()
 }
 
 pub async fn best_sell_handler<O: OrderBook>(
-    State(AppState { order_book }): State<AppState<O>>,
+    State(state): State<AppState<O>>,
+    Path(market_id): Path<String>,
 ) -> impl IntoResponse {
+    let order_book = match resolve_market(&state, &market_id).await {
+        Ok(order_book) => order_book,
+        Err(status) => return status.into_response(),
+    };
     let best_order = order_book.best_sell().await;
-    (StatusCode::OK, Json(best_order))
+    (StatusCode::OK, Json(best_order)).into_response()
+}
+
+pub async fn cancel_order_handler<O: OrderBook>(
+    State(state): State<AppState<O>>,
+    Path((market_id, order_id)): Path<(String, u64)>,
+) -> impl IntoResponse {
+    let order_book = match resolve_market(&state, &market_id).await {
+        Ok(order_book) => order_book,
+        Err(status) => return status,
+    };
+    if order_book.cancel_order(order_id).await {
+        StatusCode::OK
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+#[derive(Deserialize)]
+pub struct AmendOrderRequest {
+    pub price: u64,
+    pub quantity: u64,
+}
+
+pub async fn amend_order_handler<O: OrderBook>(
+    State(state): State<AppState<O>>,
+    Path((market_id, order_id)): Path<(String, u64)>,
+    Json(request): Json<AmendOrderRequest>,
+) -> impl IntoResponse {
+    let order_book = match resolve_market(&state, &market_id).await {
+        Ok(order_book) => order_book,
+        Err(status) => return status,
+    };
+    if order_book
+        .amend_order(order_id, request.price, request.quantity)
+        .await
+    {
+        StatusCode::OK
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+#[derive(Deserialize)]
+pub struct DepthQuery {
+    pub side: OrderSide,
+    pub levels: usize,
+}
+
+pub async fn depth_handler<O: OrderBook>(
+    State(state): State<AppState<O>>,
+    Path(market_id): Path<String>,
+    Query(query): Query<DepthQuery>,
+) -> impl IntoResponse {
+    let order_book = match resolve_market(&state, &market_id).await {
+        Ok(order_book) => order_book,
+        Err(status) => return status.into_response(),
+    };
+    let levels = order_book.depth(query.side, query.levels).await;
+    (StatusCode::OK, Json(levels)).into_response()
+}
+
+pub async fn ws_handler<O: OrderBook + Send + Sync + 'static>(
+    State(state): State<AppState<O>>,
+    Path(market_id): Path<String>,
+    ws: WebSocketUpgrade,
+) -> impl IntoResponse {
+    let order_book = match resolve_market(&state, &market_id).await {
+        Ok(order_book) => order_book,
+        Err(status) => return status.into_response(),
+    };
+    ws.on_upgrade(move |socket| stream_book_feed(socket, order_book))
+        .into_response()
+}
+
+/// Sends the initial `BookCheckpoint` (every level on both sides), then
+/// forwards every subsequent `LevelUpdate` published by the book until the
+/// client disconnects or the feed closes. A subscriber that lags behind the
+/// channel skips the updates it missed and keeps going; the client sees
+/// this as a gap in `sequence`.
+async fn stream_book_feed<O: OrderBook + Send + Sync + 'static>(
+    mut socket: WebSocket,
+    order_book: Arc<O>,
+) {
+    let mut updates = order_book.subscribe();
+
+    let mut levels = vec![];
+    for best_buy in order_book.depth(OrderSide::Buy, usize::MAX).await {
+        levels.push(Level {
+            side: OrderSide::Buy,
+            price: best_buy.price,
+            total_quantity: best_buy.total_quantity,
+        });
+    }
+    for best_sell in order_book.depth(OrderSide::Sell, usize::MAX).await {
+        levels.push(Level {
+            side: OrderSide::Sell,
+            price: best_sell.price,
+            total_quantity: best_sell.total_quantity,
+        });
+    }
+    let checkpoint = FeedMessage::BookCheckpoint {
+        sequence: order_book.current_sequence(),
+        levels,
+    };
+    if send_feed_message(&mut socket, &checkpoint).await.is_err() {
+        return;
+    }
+
+    loop {
+        let update = match updates.recv().await {
+            Ok(update) => update,
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                warn!("/ws subscriber lagged, skipped {skipped} level updates");
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+        if send_feed_message(&mut socket, &update).await.is_err() {
+            break;
+        }
+    }
+}
+
+async fn send_feed_message(socket: &mut WebSocket, message: &FeedMessage) -> Result<(), ()> {
+    let Ok(payload) = serde_json::to_string(message) else {
+        return Err(());
+    };
+    socket.send(Message::Text(payload.into())).await.map_err(|_| ())
 }
@@ -1,36 +1,61 @@
+use crate::order_book::MarketId;
 use crate::order_book::OrderBook;
 use crate::order_book::limit_order_book::LimitOrderBook;
 use axum::Router;
-use axum::routing::{get, post};
+use axum::routing::{delete, get, post};
 use log::info;
 use simple_logger::SimpleLogger;
+use std::collections::HashMap;
 use std::sync::Arc;
+use tokio::sync::RwLock;
 
 mod handlers;
 mod order_book;
 
+/// Every registered market gets its own independently-locked order book, so
+/// one process can serve many symbols concurrently.
 #[derive(Clone)]
 pub struct AppState<O: OrderBook> {
-    pub order_book: Arc<O>,
+    pub markets: Arc<RwLock<HashMap<MarketId, Arc<O>>>>,
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     SimpleLogger::new().env().init()?;
 
-    let order_book = LimitOrderBook::new();
-    let app_state = AppState {
-        order_book: Arc::new(order_book),
+    let app_state: AppState<LimitOrderBook> = AppState {
+        markets: Arc::new(RwLock::new(HashMap::new())),
     };
 
     // HTTP server to expose order book functionality
     let router = Router::new()
         .route(
-            "/place_order",
+            "/markets",
+            post(handlers::markets::register_market_handler)
+                .get(handlers::markets::list_markets_handler),
+        )
+        .route(
+            "/markets/{id}/place_order",
             post(handlers::order_book::place_order_handler),
         )
-        .route("/best_buy", get(handlers::order_book::best_buy_handler))
-        .route("/best_sell", get(handlers::order_book::best_sell_handler))
+        .route(
+            "/markets/{id}/best_buy",
+            get(handlers::order_book::best_buy_handler),
+        )
+        .route(
+            "/markets/{id}/best_sell",
+            get(handlers::order_book::best_sell_handler),
+        )
+        .route(
+            "/markets/{id}/order/{order_id}",
+            delete(handlers::order_book::cancel_order_handler)
+                .patch(handlers::order_book::amend_order_handler),
+        )
+        .route(
+            "/markets/{id}/depth",
+            get(handlers::order_book::depth_handler),
+        )
+        .route("/markets/{id}/ws", get(handlers::order_book::ws_handler))
         .with_state(app_state);
 
     info!("Starting HTTP server at 0.0.0.0:9999...");
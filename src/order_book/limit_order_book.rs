@@ -1,151 +1,490 @@
-use crate::order_book::{BestOrder, Order, OrderBook, OrderSide, Trade};
+use crate::order_book::util;
+use crate::order_book::{
+    BestOrder, FeedMessage, Level, Order, OrderBook, OrderSide, OrderType, TimeInForce, Trade,
+};
 use log::info;
+use std::collections::{BTreeMap, VecDeque};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::sync::broadcast;
 use tokio::sync::{RwLock, RwLockWriteGuard};
 
+/// Feed channel capacity: slow/disconnected subscribers lag rather than
+/// blocking matching; they simply miss messages past this many updates.
+const FEED_CHANNEL_CAPACITY: usize = 1024;
+
+/// A single FIFO price level: resting orders in time priority, plus a
+/// running total so `best_order`/`depth` don't have to re-sum on every call.
+#[derive(Debug, Default)]
+struct PriceLevel {
+    orders: VecDeque<Order>,
+    total_quantity: u64,
+}
+
+impl PriceLevel {
+    fn push_back(&mut self, order: Order) {
+        self.total_quantity += order.quantity;
+        self.orders.push_back(order);
+    }
+}
+
+/// One side of the book: a `BTreeMap` keyed by price gives O(log n)
+/// best-price lookups and ordered iteration for sweeps, while each price
+/// level's `VecDeque` preserves time priority within that level.
+type PriceBook = BTreeMap<u64, PriceLevel>;
+
+fn is_expired(order: &Order, now: u128) -> bool {
+    order.valid_to.is_some_and(|valid_to| now >= valid_to)
+}
+
 /// In-Memory Limit Order Book implementation.
 /// This is an in-memory implementation of a simple limit order book that is fully thread safe.
 /// The order book can be cloned easily, because it will point to the same underlying buy and sell orders.
 #[derive(Clone)]
 pub struct LimitOrderBook {
-    buy_orders: Arc<RwLock<Vec<Order>>>,
-    sell_orders: Arc<RwLock<Vec<Order>>>,
+    buy_orders: Arc<RwLock<PriceBook>>,
+    sell_orders: Arc<RwLock<PriceBook>>,
+    /// StopLoss/TakeProfit orders, parked here until the last trade price
+    /// crosses their trigger, at which point they are promoted as Limit
+    /// orders into `buy_orders`/`sell_orders`.
+    triggered_orders: Arc<RwLock<Vec<Order>>>,
+    last_trade_price: Arc<RwLock<Option<u64>>>,
+    /// Broadcasts `LevelUpdate`s for the `/ws` feed, see `publish_level_update`.
+    feed: broadcast::Sender<FeedMessage>,
+    feed_sequence: Arc<AtomicU64>,
+}
+
+impl Default for LimitOrderBook {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl LimitOrderBook {
     pub fn new() -> Self {
+        let (feed, _) = broadcast::channel(FEED_CHANNEL_CAPACITY);
         LimitOrderBook {
             buy_orders: Arc::new(Default::default()),
             sell_orders: Arc::new(Default::default()),
+            triggered_orders: Arc::new(Default::default()),
+            last_trade_price: Arc::new(Default::default()),
+            feed,
+            feed_sequence: Arc::new(Default::default()),
         }
     }
 
     /// Placing an order internal handler.
     async fn place_order_internal(&self, order: Order) -> Vec<Trade> {
+        self.reap_expired_orders().await;
+
+        // Stop-loss/take-profit orders don't match immediately, they wait
+        // on the sideline until a trade crosses their trigger.
+        if matches!(
+            order.order_type,
+            OrderType::StopLoss { .. } | OrderType::TakeProfit { .. }
+        ) {
+            self.triggered_orders.write().await.push(order);
+            return vec![];
+        }
+
+        let trades = self.match_and_rest(order).await;
+
+        if let Some(last_trade) = trades.last() {
+            *self.last_trade_price.write().await = Some(last_trade.price);
+            self.promote_triggered_orders(last_trade.price).await;
+        }
+
+        info!("Trades made: {trades:?}");
+        info!("Buy orders: {:?}", self.buy_orders.read().await);
+        info!("Sell orders: {:?}", self.sell_orders.read().await);
+
+        trades
+    }
+
+    /// Sweeps `order` against the opposite book and, if anything is left
+    /// over, rests it on its own side. Shared by fresh order placement and
+    /// by `promote_triggered_orders`, so a promoted StopLoss/TakeProfit
+    /// matches immediately against any crossable liquidity instead of just
+    /// resting unmatched.
+    async fn match_and_rest(&self, order: Order) -> Vec<Trade> {
+        let is_market = order.order_type == OrderType::Market;
+        let opposite_side = match order.side {
+            OrderSide::Buy => OrderSide::Sell,
+            OrderSide::Sell => OrderSide::Buy,
+        };
+
         let mut lock = match order.side {
             OrderSide::Buy => self.sell_orders.write().await,
             OrderSide::Sell => self.buy_orders.write().await,
         };
+
+        // Fill-Or-Kill is an all-or-nothing check: if the opposite book
+        // can't cover the full quantity right now, reject atomically
+        // without touching either book. This is done under the same write
+        // lock as the sweep below, so a concurrent order can't change the
+        // available liquidity between the check and the match.
+        if order.time_in_force == TimeInForce::Fok {
+            let available: u64 = match order.side {
+                OrderSide::Buy => lock
+                    .iter()
+                    .take_while(|(price, _)| is_market || **price <= order.price)
+                    .map(|(_, level)| level.total_quantity)
+                    .sum(),
+                OrderSide::Sell => lock
+                    .iter()
+                    .rev()
+                    .take_while(|(price, _)| is_market || **price >= order.price)
+                    .map(|(_, level)| level.total_quantity)
+                    .sum(),
+            };
+            if available < order.quantity {
+                return vec![];
+            }
+        }
+
         let mut trades = vec![];
         let mut remaining_quantity = order.quantity;
+        let mut touched_prices = vec![];
+
+        // best price first: ascending for the sell book (a buyer wants the
+        // cheapest sell first), descending for the buy book
+        let prices: Vec<u64> = match order.side {
+            OrderSide::Buy => lock.keys().copied().collect(),
+            OrderSide::Sell => lock.keys().rev().copied().collect(),
+        };
+
+        for price in prices {
+            if remaining_quantity == 0 {
+                break;
+            }
+            // a Market order sweeps the opposite book regardless of price
+            let order_price_ok = is_market
+                || match order.side {
+                    OrderSide::Buy => price <= order.price,
+                    OrderSide::Sell => price >= order.price,
+                };
+            if !order_price_ok {
+                break;
+            }
+
+            touched_prices.push(price);
+            let level = lock
+                .get_mut(&price)
+                .expect("price came from this book's own keys");
+
+            while remaining_quantity > 0 {
+                let Some(curr_order) = level.orders.front_mut() else {
+                    break;
+                };
 
-        for curr_order in lock.iter_mut() {
-            let order_price_ok = match order.side {
-                OrderSide::Buy => curr_order.price <= order.price,
-                OrderSide::Sell => curr_order.price >= order.price,
-            };
-            if order_price_ok && remaining_quantity > 0 {
                 // if we have more from current order, just decrease its quantity
                 if curr_order.quantity > remaining_quantity {
                     trades.push(Trade::new(
                         curr_order.id,
                         order.id,
-                        curr_order.price,
+                        price,
                         remaining_quantity,
                     ));
                     curr_order.quantity -= remaining_quantity;
+                    level.total_quantity -= remaining_quantity;
                     remaining_quantity = 0;
-                    break;
-                }
-
-                // delete current order from order book if we used all of it's quantity
-                if curr_order.quantity <= remaining_quantity {
-                    trades.push(Trade::new(
-                        curr_order.id,
-                        order.id,
-                        curr_order.price,
-                        curr_order.quantity,
-                    ));
+                } else {
+                    // delete current order from order book if we used all of it's quantity
+                    trades.push(Trade::new(curr_order.id, order.id, price, curr_order.quantity));
                     remaining_quantity -= curr_order.quantity;
-                    curr_order.quantity = 0;
+                    level.total_quantity -= curr_order.quantity;
+                    level.orders.pop_front();
                 }
-            } else {
-                break;
             }
-        }
 
-        // keep orders only whose has some remaining quantity
-        *lock = lock
-            .iter()
-            .filter(|order| order.quantity > 0)
-            .cloned()
-            .collect::<Vec<Order>>();
+            if level.orders.is_empty() {
+                lock.remove(&price);
+            }
+        }
         drop(lock);
 
+        for price in touched_prices {
+            self.publish_level_update(opposite_side.clone(), price)
+                .await;
+        }
+
         // if we have some quantity left, just add to the corresponding internal order book
-        if remaining_quantity > 0 {
+        // a Market order never rests, any unfilled remainder is cancelled; neither does
+        // an Immediate-Or-Cancel order, whose remainder is discarded instead; a Fill-Or-Kill
+        // order is rejected atomically above and so never reaches here with a remainder,
+        // but it's excluded too in case that invariant is ever violated
+        let never_rests =
+            is_market || matches!(order.time_in_force, TimeInForce::Ioc | TimeInForce::Fok);
+        if remaining_quantity > 0 && !never_rests {
             let mut lock = match order.side {
                 OrderSide::Buy => self.buy_orders.write().await,
                 OrderSide::Sell => self.sell_orders.write().await,
             };
             self.add_order(
                 &mut lock,
-                Order::new(order.id, OrderSide::Buy, order.price, remaining_quantity),
+                Order::new(
+                    order.id,
+                    order.side.clone(),
+                    OrderType::Limit,
+                    TimeInForce::Gtc,
+                    order.valid_to,
+                    order.price,
+                    remaining_quantity,
+                ),
             )
             .await;
             drop(lock);
-        }
 
-        info!("Trades made: {trades:?}");
-        info!("Buy orders: {:?}", self.buy_orders.read().await);
-        info!("Sell orders: {:?}", self.sell_orders.read().await);
+            self.publish_level_update(order.side.clone(), order.price)
+                .await;
+        }
 
         trades
     }
 
-    /// Collects the best order from Buy or Sell internal order books.
-    /// If any found with the same price, just add it's quantity to the total quantity.
+    /// Publishes the current aggregated quantity at `price` on `side` to the
+    /// feed, stamped with the next sequence number. A quantity of `0` tells
+    /// subscribers the level is gone.
+    async fn publish_level_update(&self, side: OrderSide, price: u64) {
+        let total_quantity = self.level_quantity(&side, price).await;
+        let sequence = self.feed_sequence.fetch_add(1, Ordering::SeqCst) + 1;
+        // no subscribers is a normal state (nobody connected to `/ws` yet)
+        let _ = self.feed.send(FeedMessage::LevelUpdate {
+            sequence,
+            level: Level {
+                side,
+                price,
+                total_quantity,
+            },
+        });
+    }
+
+    /// Drops resting and parked orders whose `valid_to` has passed, so they
+    /// can no longer match or be reported once expired.
+    async fn reap_expired_orders(&self) {
+        let now = util::current_unix_timestamp();
+
+        self.reap_side(&self.buy_orders, OrderSide::Buy, now).await;
+        self.reap_side(&self.sell_orders, OrderSide::Sell, now).await;
+        self.triggered_orders
+            .write()
+            .await
+            .retain(|order| !is_expired(order, now));
+    }
+
+    /// Prunes expired orders from a single side's book, dropping any price
+    /// level left empty and publishing a level update for every price whose
+    /// resting quantity changed as a result (a `0` quantity tells subscribers
+    /// the level is gone, same as a fully-filled level).
+    async fn reap_side(&self, book: &RwLock<PriceBook>, side: OrderSide, now: u128) {
+        let mut lock = book.write().await;
+        let mut changed_prices = vec![];
+        let emptied_prices: Vec<u64> = lock
+            .iter_mut()
+            .filter_map(|(price, level)| {
+                let before = level.orders.len();
+                level.orders.retain(|order| !is_expired(order, now));
+                if level.orders.len() != before {
+                    level.total_quantity = level.orders.iter().map(|order| order.quantity).sum();
+                    changed_prices.push(*price);
+                }
+                level.orders.is_empty().then_some(*price)
+            })
+            .collect();
+        for price in emptied_prices {
+            lock.remove(&price);
+        }
+        drop(lock);
+
+        for price in changed_prices {
+            self.publish_level_update(side.clone(), price).await;
+        }
+    }
+
+    /// Returns the cached resting quantity at `price` on `side` (`0` if there
+    /// is no level at that price).
+    async fn level_quantity(&self, side: &OrderSide, price: u64) -> u64 {
+        let lock = match side {
+            OrderSide::Buy => self.buy_orders.read().await,
+            OrderSide::Sell => self.sell_orders.read().await,
+        };
+        lock.get(&price).map_or(0, |level| level.total_quantity)
+    }
+
+    /// Moves any parked StopLoss/TakeProfit order whose trigger the last
+    /// trade price has crossed into the active book, matching it against
+    /// crossable liquidity the same way a fresh order would (that's the
+    /// whole point of the trigger firing) before any remainder rests as a
+    /// Limit order.
+    ///
+    /// Direction depends on both the order type and which side it's
+    /// protecting: a Sell StopLoss protects a long and fires as price falls
+    /// to or below `trigger`, while a Buy StopLoss protects a short and
+    /// fires as price rises to or above `trigger` — TakeProfit is the
+    /// mirror image of that.
+    fn promote_triggered_orders(
+        &self,
+        last_trade_price: u64,
+    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = ()> + Send + '_>> {
+        Box::pin(async move {
+            let mut triggered_lock = self.triggered_orders.write().await;
+            let mut to_promote = vec![];
+            let mut still_parked = vec![];
+            for order in triggered_lock.drain(..) {
+                let crosses_trigger = match (&order.order_type, &order.side) {
+                    (OrderType::StopLoss { trigger }, OrderSide::Sell) => {
+                        last_trade_price <= *trigger
+                    }
+                    (OrderType::StopLoss { trigger }, OrderSide::Buy) => {
+                        last_trade_price >= *trigger
+                    }
+                    (OrderType::TakeProfit { trigger }, OrderSide::Sell) => {
+                        last_trade_price >= *trigger
+                    }
+                    (OrderType::TakeProfit { trigger }, OrderSide::Buy) => {
+                        last_trade_price <= *trigger
+                    }
+                    _ => false,
+                };
+                if crosses_trigger {
+                    to_promote.push(order);
+                } else {
+                    still_parked.push(order);
+                }
+            }
+            *triggered_lock = still_parked;
+            drop(triggered_lock);
+
+            for mut order in to_promote {
+                order.order_type = OrderType::Limit;
+                let trades = self.match_and_rest(order).await;
+                if let Some(last_trade) = trades.last() {
+                    *self.last_trade_price.write().await = Some(last_trade.price);
+                    self.promote_triggered_orders(last_trade.price).await;
+                }
+            }
+        })
+    }
+
+    /// Looks up the best price level on `side`, using the cached per-level
+    /// total instead of re-summing its resting orders.
     async fn best_order(&self, side: OrderSide) -> Option<BestOrder> {
+        self.reap_expired_orders().await;
+
         let lock = match side {
             OrderSide::Buy => self.buy_orders.read().await,
             OrderSide::Sell => self.sell_orders.read().await,
         };
 
-        if lock.is_empty() {
-            return None;
-        }
-        let mut best_order = BestOrder {
-            price: lock[0].price,
-            total_quantity: lock[0].quantity,
+        // highest price is best for Buy, lowest price is best for Sell
+        let (price, level) = match side {
+            OrderSide::Buy => lock.iter().next_back()?,
+            OrderSide::Sell => lock.iter().next()?,
         };
-        for order in lock.iter() {
-            if order.price == best_order.price && order.id != lock[0].id {
-                best_order.total_quantity += order.quantity;
-            }
-        }
 
-        Some(best_order)
+        Some(BestOrder {
+            price: *price,
+            total_quantity: level.total_quantity,
+        })
     }
 
-    /// Adding a new order to the corresponding Buy or Sell internal order book.
-    /// After any of the additions there is a sorting to prepare for next matching.
-    async fn add_order(&self, lock: &mut RwLockWriteGuard<'_, Vec<Order>>, order: Order) {
-        match order.side {
-            OrderSide::Buy => {
-                lock.push(order);
-                lock.sort_by(|a, b| {
-                    // if prices are the same, older order wins, so we follow Price-time priority
-                    if b.price == a.price {
-                        return a.timestamp.cmp(&b.timestamp);
-                    }
-                    // ordering reverse by price to get the best price leveled order first
-                    // for Sell orders
-                    b.price.cmp(&a.price)
-                });
-            }
-            OrderSide::Sell => {
-                lock.push(order);
-                lock.sort_by(|a, b| {
-                    // if prices are the same, older order wins, so we follow Price-time priority
-                    if b.price == a.price {
-                        return a.timestamp.cmp(&b.timestamp);
-                    }
-                    // ordering incrementally by price to get the best price leveled order first
-                    // for Buy orders
-                    a.price.cmp(&b.price)
-                });
+    /// Cancels a resting order on a single side, scanning price levels for
+    /// the first order whose `id` matches and removing it in place.
+    /// Returns the price of the level it was removed from, if found.
+    async fn cancel_order_on_side(
+        &self,
+        lock: &mut RwLockWriteGuard<'_, PriceBook>,
+        order_id: u64,
+    ) -> Option<u64> {
+        let (price, pos) = lock.iter().find_map(|(price, level)| {
+            level
+                .orders
+                .iter()
+                .position(|order| order.id == order_id)
+                .map(|pos| (*price, pos))
+        })?;
+
+        let level = lock
+            .get_mut(&price)
+            .expect("price came from this book's own keys");
+        let removed = level
+            .orders
+            .remove(pos)
+            .expect("pos came from this level's own orders");
+        level.total_quantity -= removed.quantity;
+        if level.orders.is_empty() {
+            lock.remove(&price);
+        }
+        Some(price)
+    }
+
+    /// Amends a resting order on a single side.
+    /// If the price is unchanged and the quantity is only reduced, the order
+    /// keeps its place in the queue. Otherwise it is removed and re-added at
+    /// the back of its (possibly new) price level, losing time priority.
+    /// Amending the quantity down to `0` is treated as a cancellation: the
+    /// order is removed outright rather than left resting with nothing to
+    /// fill, the same way `cancel_order_on_side` removes it.
+    /// Returns the order's old price and, if it moved to a new price level,
+    /// that new price as well — both levels' resting quantity changed.
+    async fn amend_order_on_side(
+        &self,
+        lock: &mut RwLockWriteGuard<'_, PriceBook>,
+        order_id: u64,
+        new_price: u64,
+        new_quantity: u64,
+    ) -> Option<(u64, Option<u64>)> {
+        let (price, pos) = lock.iter().find_map(|(price, level)| {
+            level
+                .orders
+                .iter()
+                .position(|order| order.id == order_id)
+                .map(|pos| (*price, pos))
+        })?;
+
+        let level = lock
+            .get_mut(&price)
+            .expect("price came from this book's own keys");
+
+        if new_quantity == 0 {
+            let removed = level
+                .orders
+                .remove(pos)
+                .expect("pos came from this level's own orders");
+            level.total_quantity -= removed.quantity;
+            if level.orders.is_empty() {
+                lock.remove(&price);
             }
+            return Some((price, None));
+        }
+
+        let keeps_priority = price == new_price && new_quantity <= level.orders[pos].quantity;
+        if keeps_priority {
+            level.total_quantity -= level.orders[pos].quantity - new_quantity;
+            level.orders[pos].quantity = new_quantity;
+            return Some((price, None));
         }
+
+        let mut order = level
+            .orders
+            .remove(pos)
+            .expect("pos came from this level's own orders");
+        level.total_quantity -= order.quantity;
+        if level.orders.is_empty() {
+            lock.remove(&price);
+        }
+
+        order.price = new_price;
+        order.quantity = new_quantity;
+        order.timestamp = util::current_unix_timestamp();
+        self.add_order(lock, order).await;
+        Some((price, (new_price != price).then_some(new_price)))
+    }
+
+    /// Adds a resting order to its price level's FIFO queue, creating the
+    /// level if this is the first order at that price.
+    async fn add_order(&self, lock: &mut RwLockWriteGuard<'_, PriceBook>, order: Order) {
+        lock.entry(order.price).or_default().push_back(order);
     }
 }
 
@@ -162,17 +501,97 @@ impl OrderBook for LimitOrderBook {
     async fn best_sell(&self) -> Option<BestOrder> {
         self.best_order(OrderSide::Sell).await
     }
+
+    async fn cancel_order(&self, order_id: u64) -> bool {
+        let mut buy_lock = self.buy_orders.write().await;
+        if let Some(price) = self.cancel_order_on_side(&mut buy_lock, order_id).await {
+            drop(buy_lock);
+            self.publish_level_update(OrderSide::Buy, price).await;
+            return true;
+        }
+        drop(buy_lock);
+
+        let mut sell_lock = self.sell_orders.write().await;
+        if let Some(price) = self.cancel_order_on_side(&mut sell_lock, order_id).await {
+            drop(sell_lock);
+            self.publish_level_update(OrderSide::Sell, price).await;
+            return true;
+        }
+        false
+    }
+
+    async fn amend_order(&self, order_id: u64, new_price: u64, new_quantity: u64) -> bool {
+        let mut buy_lock = self.buy_orders.write().await;
+        if let Some((price, moved_to)) = self
+            .amend_order_on_side(&mut buy_lock, order_id, new_price, new_quantity)
+            .await
+        {
+            drop(buy_lock);
+            self.publish_level_update(OrderSide::Buy, price).await;
+            if let Some(moved_to) = moved_to {
+                self.publish_level_update(OrderSide::Buy, moved_to).await;
+            }
+            return true;
+        }
+        drop(buy_lock);
+
+        let mut sell_lock = self.sell_orders.write().await;
+        if let Some((price, moved_to)) = self
+            .amend_order_on_side(&mut sell_lock, order_id, new_price, new_quantity)
+            .await
+        {
+            drop(sell_lock);
+            self.publish_level_update(OrderSide::Sell, price).await;
+            if let Some(moved_to) = moved_to {
+                self.publish_level_update(OrderSide::Sell, moved_to).await;
+            }
+            return true;
+        }
+        false
+    }
+
+    async fn depth(&self, side: OrderSide, levels: usize) -> Vec<BestOrder> {
+        self.reap_expired_orders().await;
+
+        let lock = match side {
+            OrderSide::Buy => self.buy_orders.read().await,
+            OrderSide::Sell => self.sell_orders.read().await,
+        };
+
+        // each map entry is already one L2 level, best price first
+        let to_best_order = |(price, level): (&u64, &PriceLevel)| BestOrder {
+            price: *price,
+            total_quantity: level.total_quantity,
+        };
+        match side {
+            OrderSide::Buy => lock.iter().rev().take(levels).map(to_best_order).collect(),
+            OrderSide::Sell => lock.iter().take(levels).map(to_best_order).collect(),
+        }
+    }
+
+    fn subscribe(&self) -> broadcast::Receiver<FeedMessage> {
+        self.feed.subscribe()
+    }
+
+    fn current_sequence(&self) -> u64 {
+        self.feed_sequence.load(Ordering::SeqCst)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::LimitOrderBook;
-    use crate::order_book::{Order, OrderBook, OrderSide, Trade};
+    use crate::order_book::{
+        BestOrder, FeedMessage, Order, OrderBook, OrderSide, OrderType, TimeInForce, Trade,
+    };
 
     fn order(id: u64, side: OrderSide, price: u64, quantity: u64, timestamp: u128) -> Order {
         Order {
             id,
             side,
+            order_type: OrderType::Limit,
+            time_in_force: TimeInForce::Gtc,
+            valid_to: None,
             price,
             quantity,
             timestamp,
@@ -284,7 +703,7 @@ mod tests {
             .best_sell()
             .await
             .expect("One sell remainder expected");
-        assert_eq!(best_sell.price, 100);
+        assert_eq!(best_sell.price, 101);
         assert_eq!(best_sell.total_quantity, 1);
 
         let best_buy = order_book.best_buy().await;
@@ -326,4 +745,513 @@ mod tests {
         let best_sell = order_book.best_sell().await;
         assert!(best_sell.is_none(), "No resting sells expected");
     }
+
+    #[tokio::test]
+    async fn cancel_order_removes_resting_order() {
+        let order_book = LimitOrderBook::new();
+
+        let _ = order_book
+            .place_order(order(1, OrderSide::Buy, 100, 5, 1))
+            .await;
+
+        assert!(order_book.cancel_order(1).await);
+
+        let best_buy = order_book.best_buy().await;
+        assert!(best_buy.is_none(), "Cancelled order should be gone");
+    }
+
+    #[tokio::test]
+    async fn cancel_order_returns_false_when_not_found() {
+        let order_book = LimitOrderBook::new();
+
+        assert!(!order_book.cancel_order(42).await);
+    }
+
+    #[tokio::test]
+    async fn amend_order_keeps_priority_on_quantity_decrease() {
+        let order_book = LimitOrderBook::new();
+
+        let _ = order_book
+            .place_order(order(1, OrderSide::Buy, 100, 5, 1))
+            .await;
+        let _ = order_book
+            .place_order(order(2, OrderSide::Buy, 100, 5, 2))
+            .await;
+
+        assert!(order_book.amend_order(1, 100, 2).await);
+
+        let trades = order_book
+            .place_order(order(3, OrderSide::Sell, 100, 3, 3))
+            .await;
+        assert_eq!(
+            trades,
+            vec![Trade::new(1, 3, 100, 2), Trade::new(2, 3, 100, 1)],
+            "Amended order should still be filled first"
+        );
+    }
+
+    #[tokio::test]
+    async fn amend_order_moves_to_new_price_level_on_price_change() {
+        let order_book = LimitOrderBook::new();
+
+        let _ = order_book
+            .place_order(order(1, OrderSide::Buy, 100, 5, 1))
+            .await;
+        let _ = order_book
+            .place_order(order(2, OrderSide::Buy, 100, 5, 2))
+            .await;
+
+        assert!(order_book.amend_order(1, 101, 5).await);
+
+        let best_buy = order_book
+            .best_buy()
+            .await
+            .expect("Amended order should rest at the new price");
+        assert_eq!(best_buy.price, 101);
+        assert_eq!(best_buy.total_quantity, 5);
+    }
+
+    #[tokio::test]
+    async fn amend_order_returns_false_when_not_found() {
+        let order_book = LimitOrderBook::new();
+
+        assert!(!order_book.amend_order(42, 100, 1).await);
+    }
+
+    #[tokio::test]
+    async fn amend_order_to_zero_quantity_cancels_it_outright() {
+        let order_book = LimitOrderBook::new();
+
+        let _ = order_book
+            .place_order(order(1, OrderSide::Buy, 100, 5, 1))
+            .await;
+
+        assert!(order_book.amend_order(1, 100, 0).await);
+
+        let best_buy = order_book.best_buy().await;
+        assert!(
+            best_buy.is_none(),
+            "amending to zero quantity should leave no ghost level behind"
+        );
+
+        let trades = order_book
+            .place_order(order(2, OrderSide::Sell, 100, 3, 2))
+            .await;
+        assert_eq!(
+            trades.len(),
+            0,
+            "a crossing sell must not match the cancelled zero-quantity order"
+        );
+    }
+
+    #[tokio::test]
+    async fn market_order_sweeps_book_and_never_rests() {
+        let order_book = LimitOrderBook::new();
+
+        let _ = order_book
+            .place_order(order(1, OrderSide::Sell, 100, 3, 1))
+            .await;
+        let _ = order_book
+            .place_order(order(2, OrderSide::Sell, 100, 4, 2))
+            .await;
+
+        let market_buy = Order {
+            id: 3,
+            side: OrderSide::Buy,
+            order_type: OrderType::Market,
+            time_in_force: TimeInForce::Gtc,
+            valid_to: None,
+            price: 0,
+            quantity: 10,
+            timestamp: 3,
+        };
+        let trades = order_book.place_order(market_buy).await;
+        assert_eq!(
+            trades,
+            vec![Trade::new(1, 3, 100, 3), Trade::new(2, 3, 100, 4)],
+            "Market order should sweep all resting liquidity regardless of its own price"
+        );
+
+        let best_buy = order_book.best_buy().await;
+        assert!(
+            best_buy.is_none(),
+            "Unfilled Market remainder must never rest"
+        );
+    }
+
+    #[tokio::test]
+    async fn stop_loss_is_promoted_once_last_trade_crosses_trigger() {
+        let order_book = LimitOrderBook::new();
+
+        let stop_loss = Order {
+            id: 1,
+            side: OrderSide::Sell,
+            order_type: OrderType::StopLoss { trigger: 100 },
+            time_in_force: TimeInForce::Gtc,
+            valid_to: None,
+            price: 99,
+            quantity: 5,
+            timestamp: 1,
+        };
+        let trades = order_book.place_order(stop_loss).await;
+        assert_eq!(trades.len(), 0, "StopLoss should not match yet");
+        assert!(order_book.best_sell().await.is_none());
+
+        // a trade at 100 crosses the trigger and promotes the stop-loss as a resting Limit sell
+        let _ = order_book
+            .place_order(order(2, OrderSide::Buy, 100, 1, 2))
+            .await;
+        let _ = order_book
+            .place_order(order(3, OrderSide::Sell, 100, 1, 3))
+            .await;
+
+        let best_sell = order_book
+            .best_sell()
+            .await
+            .expect("StopLoss should now rest as a Limit sell");
+        assert_eq!(best_sell.price, 99);
+        assert_eq!(best_sell.total_quantity, 5);
+    }
+
+    #[tokio::test]
+    async fn buy_side_stop_loss_is_promoted_when_price_rises_to_trigger() {
+        let order_book = LimitOrderBook::new();
+
+        // a Buy StopLoss covers a short position, so it fires as price rises
+        let stop_loss = Order {
+            id: 1,
+            side: OrderSide::Buy,
+            order_type: OrderType::StopLoss { trigger: 100 },
+            time_in_force: TimeInForce::Gtc,
+            valid_to: None,
+            price: 101,
+            quantity: 5,
+            timestamp: 1,
+        };
+        let trades = order_book.place_order(stop_loss).await;
+        assert_eq!(trades.len(), 0, "StopLoss should not match yet");
+        assert!(order_book.best_buy().await.is_none());
+
+        // a trade at 100 crosses the trigger and promotes the stop-loss as a resting Limit buy
+        let _ = order_book
+            .place_order(order(2, OrderSide::Sell, 100, 1, 2))
+            .await;
+        let _ = order_book
+            .place_order(order(3, OrderSide::Buy, 100, 1, 3))
+            .await;
+
+        let best_buy = order_book
+            .best_buy()
+            .await
+            .expect("StopLoss should now rest as a Limit buy");
+        assert_eq!(best_buy.price, 101);
+        assert_eq!(best_buy.total_quantity, 5);
+    }
+
+    #[tokio::test]
+    async fn promoted_stop_loss_matches_immediately_against_resting_liquidity() {
+        let order_book = LimitOrderBook::new();
+
+        let stop_loss = Order {
+            id: 1,
+            side: OrderSide::Sell,
+            order_type: OrderType::StopLoss { trigger: 100 },
+            time_in_force: TimeInForce::Gtc,
+            valid_to: None,
+            price: 99,
+            quantity: 5,
+            timestamp: 1,
+        };
+        let _ = order_book.place_order(stop_loss).await;
+
+        // resting buy liquidity at a price that would cross the promoted stop-loss
+        let _ = order_book
+            .place_order(order(2, OrderSide::Buy, 99, 5, 2))
+            .await;
+
+        // the trade that crosses the trigger
+        let _ = order_book
+            .place_order(order(3, OrderSide::Buy, 100, 1, 3))
+            .await;
+        let _ = order_book
+            .place_order(order(4, OrderSide::Sell, 100, 1, 4))
+            .await;
+
+        assert!(
+            order_book.best_sell().await.is_none(),
+            "promoted StopLoss should have matched the resting buy instead of just resting"
+        );
+        assert!(
+            order_book.best_buy().await.is_none(),
+            "the resting buy it matched against should be fully consumed"
+        );
+    }
+
+    #[tokio::test]
+    async fn depth_aggregates_same_price_orders_into_levels() {
+        let order_book = LimitOrderBook::new();
+
+        let _ = order_book
+            .place_order(order(1, OrderSide::Buy, 100, 2, 1))
+            .await;
+        let _ = order_book
+            .place_order(order(2, OrderSide::Buy, 100, 3, 2))
+            .await;
+        let _ = order_book
+            .place_order(order(3, OrderSide::Buy, 99, 1, 3))
+            .await;
+
+        let depth = order_book.depth(OrderSide::Buy, 10).await;
+        assert_eq!(
+            depth,
+            vec![
+                BestOrder {
+                    price: 100,
+                    total_quantity: 5
+                },
+                BestOrder {
+                    price: 99,
+                    total_quantity: 1
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn depth_is_capped_at_requested_levels() {
+        let order_book = LimitOrderBook::new();
+
+        let _ = order_book
+            .place_order(order(1, OrderSide::Buy, 100, 1, 1))
+            .await;
+        let _ = order_book
+            .place_order(order(2, OrderSide::Buy, 99, 1, 2))
+            .await;
+        let _ = order_book
+            .place_order(order(3, OrderSide::Buy, 98, 1, 3))
+            .await;
+
+        let depth = order_book.depth(OrderSide::Buy, 2).await;
+        assert_eq!(depth.len(), 2);
+        assert_eq!(depth[0].price, 100);
+        assert_eq!(depth[1].price, 99);
+    }
+
+    #[tokio::test]
+    async fn subscribers_receive_level_updates_with_increasing_sequence() {
+        let order_book = LimitOrderBook::new();
+        let mut feed = order_book.subscribe();
+
+        let _ = order_book
+            .place_order(order(1, OrderSide::Buy, 100, 5, 1))
+            .await;
+
+        let update = feed.recv().await.expect("should receive a level update");
+        match update {
+            FeedMessage::LevelUpdate { sequence, level } => {
+                assert_eq!(sequence, 1);
+                assert_eq!(level.side, OrderSide::Buy);
+                assert_eq!(level.price, 100);
+                assert_eq!(level.total_quantity, 5);
+            }
+            other => panic!("expected a LevelUpdate, got {other:?}"),
+        }
+
+        let _ = order_book.cancel_order(1).await;
+        let update = feed
+            .recv()
+            .await
+            .expect("cancel_order should publish a level update");
+        match update {
+            FeedMessage::LevelUpdate { sequence, level } => {
+                assert_eq!(sequence, 2);
+                assert_eq!(level.side, OrderSide::Buy);
+                assert_eq!(level.price, 100);
+                assert_eq!(
+                    level.total_quantity, 0,
+                    "level is gone once its only order is cancelled"
+                );
+            }
+            other => panic!("expected a LevelUpdate, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn amend_order_publishes_level_updates_for_both_old_and_new_price() {
+        let order_book = LimitOrderBook::new();
+        let mut feed = order_book.subscribe();
+
+        let _ = order_book
+            .place_order(order(1, OrderSide::Buy, 100, 5, 1))
+            .await;
+        let _ = feed.recv().await.expect("initial place publishes");
+
+        assert!(order_book.amend_order(1, 101, 5).await);
+
+        let first = feed
+            .recv()
+            .await
+            .expect("amend should publish the old price going to zero");
+        let second = feed
+            .recv()
+            .await
+            .expect("amend should publish the new price's quantity");
+        match (first, second) {
+            (
+                FeedMessage::LevelUpdate { level: old, .. },
+                FeedMessage::LevelUpdate { level: new, .. },
+            ) => {
+                assert_eq!(old.price, 100);
+                assert_eq!(old.total_quantity, 0);
+                assert_eq!(new.price, 101);
+                assert_eq!(new.total_quantity, 5);
+            }
+            other => panic!("expected two LevelUpdates, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn ioc_order_fills_partially_and_discards_remainder() {
+        let order_book = LimitOrderBook::new();
+
+        let _ = order_book
+            .place_order(order(1, OrderSide::Sell, 100, 3, 1))
+            .await;
+
+        let ioc_buy = Order {
+            id: 2,
+            side: OrderSide::Buy,
+            order_type: OrderType::Limit,
+            time_in_force: TimeInForce::Ioc,
+            valid_to: None,
+            price: 100,
+            quantity: 5,
+            timestamp: 2,
+        };
+        let trades = order_book.place_order(ioc_buy).await;
+        assert_eq!(trades, vec![Trade::new(1, 2, 100, 3)]);
+
+        let best_buy = order_book.best_buy().await;
+        assert!(best_buy.is_none(), "IOC remainder must never rest");
+    }
+
+    #[tokio::test]
+    async fn fok_order_rejected_when_liquidity_insufficient() {
+        let order_book = LimitOrderBook::new();
+
+        let _ = order_book
+            .place_order(order(1, OrderSide::Sell, 100, 3, 1))
+            .await;
+
+        let fok_buy = Order {
+            id: 2,
+            side: OrderSide::Buy,
+            order_type: OrderType::Limit,
+            time_in_force: TimeInForce::Fok,
+            valid_to: None,
+            price: 100,
+            quantity: 5,
+            timestamp: 2,
+        };
+        let trades = order_book.place_order(fok_buy).await;
+        assert_eq!(
+            trades.len(),
+            0,
+            "FOK should reject atomically when it can't be filled in full"
+        );
+
+        let best_sell = order_book
+            .best_sell()
+            .await
+            .expect("resting sell must be untouched by a rejected FOK");
+        assert_eq!(best_sell.price, 100);
+        assert_eq!(best_sell.total_quantity, 3);
+    }
+
+    #[tokio::test]
+    async fn fok_order_fills_fully_when_liquidity_sufficient() {
+        let order_book = LimitOrderBook::new();
+
+        let _ = order_book
+            .place_order(order(1, OrderSide::Sell, 100, 5, 1))
+            .await;
+
+        let fok_buy = Order {
+            id: 2,
+            side: OrderSide::Buy,
+            order_type: OrderType::Limit,
+            time_in_force: TimeInForce::Fok,
+            valid_to: None,
+            price: 100,
+            quantity: 5,
+            timestamp: 2,
+        };
+        let trades = order_book.place_order(fok_buy).await;
+        assert_eq!(trades, vec![Trade::new(1, 2, 100, 5)]);
+    }
+
+    #[tokio::test]
+    async fn expired_order_is_reaped_before_matching() {
+        let order_book = LimitOrderBook::new();
+
+        let expired_sell = Order {
+            id: 1,
+            side: OrderSide::Sell,
+            order_type: OrderType::Limit,
+            time_in_force: TimeInForce::Gtc,
+            valid_to: Some(1), // already in the past by the time any reap runs
+            price: 100,
+            quantity: 5,
+            timestamp: 1,
+        };
+        let _ = order_book.place_order(expired_sell).await;
+
+        let trades = order_book
+            .place_order(order(2, OrderSide::Buy, 100, 5, 2))
+            .await;
+        assert_eq!(
+            trades.len(),
+            0,
+            "expired order should be reaped before it can match"
+        );
+        assert!(order_book.best_sell().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn reaping_an_expired_order_publishes_a_level_update() {
+        let order_book = LimitOrderBook::new();
+
+        let expired_sell = Order {
+            id: 1,
+            side: OrderSide::Sell,
+            order_type: OrderType::Limit,
+            time_in_force: TimeInForce::Gtc,
+            valid_to: Some(1), // already in the past by the time any reap runs
+            price: 100,
+            quantity: 5,
+            timestamp: 1,
+        };
+        let _ = order_book.place_order(expired_sell).await;
+
+        let mut feed = order_book.subscribe();
+        let _ = order_book
+            .place_order(order(2, OrderSide::Buy, 100, 5, 2))
+            .await;
+
+        let update = feed
+            .recv()
+            .await
+            .expect("reaping the expired sell should publish a level update");
+        match update {
+            FeedMessage::LevelUpdate { level, .. } => {
+                assert_eq!(level.side, OrderSide::Sell);
+                assert_eq!(level.price, 100);
+                assert_eq!(
+                    level.total_quantity, 0,
+                    "level is gone once its only order expires"
+                );
+            }
+            other => panic!("expected a LevelUpdate, got {other:?}"),
+        }
+    }
 }
@@ -16,4 +16,25 @@ pub trait OrderBook {
     /// Returns the best priced Sell order
     /// (including same price orders added to quantity).
     async fn best_sell(&self) -> Option<BestOrder>;
+
+    /// Cancels a resting order by id, scanning both the Buy and Sell books.
+    /// Returns `true` if the order was found and removed, `false` otherwise.
+    async fn cancel_order(&self, order_id: u64) -> bool;
+
+    /// Amends a resting order's price and/or quantity.
+    /// A pure quantity decrease keeps the order's time priority in place;
+    /// a price change or quantity increase re-inserts it at the back of its
+    /// (possibly new) price level, so it loses priority.
+    /// Returns `true` if the order was found and amended, `false` otherwise.
+    async fn amend_order(&self, order_id: u64, new_price: u64, new_quantity: u64) -> bool;
+
+    /// Returns the top `levels` price levels for a side, aggregated L2-style:
+    /// one `{ price, total_quantity }` pair per distinct price, best first.
+    async fn depth(&self, side: OrderSide, levels: usize) -> Vec<BestOrder>;
+
+    /// Subscribes to the book's live level-update feed (see the `/ws` handler).
+    fn subscribe(&self) -> tokio::sync::broadcast::Receiver<FeedMessage>;
+
+    /// The feed's current sequence number, used to stamp the initial checkpoint.
+    fn current_sequence(&self) -> u64;
 }
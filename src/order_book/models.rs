@@ -1,27 +1,81 @@
 use crate::order_book::util;
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
 
-#[derive(PartialEq, Clone, Debug, Deserialize)]
+#[derive(PartialEq, Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum OrderSide {
     Buy,
     Sell,
 }
 
+/// The order-type taxonomy supported by the book, following the shape used
+/// by mainstream exchange APIs.
+#[derive(PartialEq, Clone, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderType {
+    /// Rests on the book at `price` until filled or cancelled.
+    Limit,
+    /// Sweeps the opposite book from best price outward until filled;
+    /// never rests, any unfilled remainder is cancelled.
+    Market,
+    /// Parked until the last trade price crosses `trigger` against the
+    /// protected position, then promoted into the active book as a limit
+    /// order: for a Sell (protecting a long) that's the price falling to
+    /// or below `trigger`; for a Buy (protecting a short) it's the price
+    /// rising to or above `trigger`.
+    StopLoss { trigger: u64 },
+    /// Parked until the last trade price crosses `trigger` in the
+    /// position's favour, then promoted into the active book as a limit
+    /// order: for a Sell (taking profit on a long) that's the price rising
+    /// to or above `trigger`; for a Buy (taking profit on a short) it's
+    /// the price falling to or below `trigger`.
+    TakeProfit { trigger: u64 },
+}
+
+/// Governs how an order behaves if it can't be fully matched on arrival.
+#[derive(PartialEq, Clone, Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeInForce {
+    /// Good-Til-Cancelled: any unfilled remainder rests on the book.
+    Gtc,
+    /// Immediate-Or-Cancel: fills as much as possible, the remainder is discarded.
+    Ioc,
+    /// Fill-Or-Kill: rejected atomically (no trades at all) unless it can be filled in full.
+    Fok,
+}
+
 #[derive(Clone, Debug)]
 pub struct Order {
     pub id: u64,
     pub side: OrderSide,
+    pub order_type: OrderType,
+    pub time_in_force: TimeInForce,
+    /// Unix-millis expiry; once past, the order is reaped before it can match.
+    pub valid_to: Option<u128>,
     pub price: u64,
     pub quantity: u64,
     pub timestamp: u128,
 }
 
 impl Order {
-    pub fn new(id: u64, side: OrderSide, price: u64, quantity: u64) -> Self {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        id: u64,
+        side: OrderSide,
+        order_type: OrderType,
+        time_in_force: TimeInForce,
+        valid_to: Option<u128>,
+        price: u64,
+        quantity: u64,
+    ) -> Self {
         Order {
             id,
             side,
+            order_type,
+            time_in_force,
+            valid_to,
             price,
             quantity,
             timestamp: util::current_unix_timestamp(),
@@ -29,6 +83,40 @@ impl Order {
     }
 }
 
+/// Identifies a market by its base/quote trading pair, e.g. `base: "BTC",
+/// quote: "USD"`. Doubles as the `/markets/{id}` path segment via its
+/// `Display`/`FromStr`, which render and parse it as `BASE-QUOTE`.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Deserialize, Serialize)]
+pub struct MarketId {
+    pub base: String,
+    pub quote: String,
+}
+
+impl MarketId {
+    pub fn new(base: String, quote: String) -> Self {
+        MarketId { base, quote }
+    }
+}
+
+impl fmt::Display for MarketId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.base, self.quote)
+    }
+}
+
+/// Returned when a `/markets/{id}` path segment isn't a valid `BASE-QUOTE` pair.
+#[derive(Debug)]
+pub struct InvalidMarketId;
+
+impl FromStr for MarketId {
+    type Err = InvalidMarketId;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (base, quote) = s.split_once('-').ok_or(InvalidMarketId)?;
+        Ok(MarketId::new(base.to_string(), quote.to_string()))
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize)]
 pub struct Trade {
     pub maker_id: u64,
@@ -48,8 +136,28 @@ impl Trade {
     }
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, PartialEq, Serialize)]
 pub struct BestOrder {
     pub price: u64,
     pub total_quantity: u64,
 }
+
+/// A single aggregated price level on one side of the book, as streamed over `/ws`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Level {
+    pub side: OrderSide,
+    pub price: u64,
+    pub total_quantity: u64,
+}
+
+/// A message on the `/ws` feed. Every message carries a monotonically
+/// increasing `sequence` number so clients can detect gaps.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FeedMessage {
+    /// Sent once on connect: every price level on both sides.
+    BookCheckpoint { sequence: u64, levels: Vec<Level> },
+    /// Sent whenever a level changes. `total_quantity` of `0` means the
+    /// level is gone and should be removed from the client's view.
+    LevelUpdate { sequence: u64, level: Level },
+}